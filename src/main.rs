@@ -20,6 +20,34 @@ const BOUNCE_DAMPING: f32 = 0.7;
 // Random initial velocity range
 const INITIAL_VELOCITY_RANGE: f32 = 50.0;
 
+// Flocking mode constants (boids rules: cohesion, separation, alignment).
+const FLOCK_RULE1_DISTANCE: f32 = 50.0;
+const FLOCK_RULE2_DISTANCE: f32 = 12.0;
+const FLOCK_RULE3_DISTANCE: f32 = 50.0;
+const FLOCK_RULE1_SCALE: f32 = 0.01;
+const FLOCK_RULE2_SCALE: f32 = 0.2;
+const FLOCK_RULE3_SCALE: f32 = 0.1;
+const FLOCK_MAX_SPEED: f32 = 150.0;
+
+const SIM_MODE_GRAVITY: u32 = 0;
+const SIM_MODE_FLOCKING: u32 = 1;
+
+// Sized so a 3x3 block of cells always covers the largest flocking rule
+// radius, which is what lets the neighbor scan skip everything else.
+const GRID_CELL_SIZE: f32 = FLOCK_RULE1_DISTANCE;
+
+// Workgroup size of the per-particle and per-cell compute kernels in
+// shader.wgsl. The grid prefix sum is the exception: it runs as a single
+// invocation.
+const WORKGROUP_SIZE: u32 = 64;
+
+// Particles render into this offscreen format instead of the swapchain, so
+// bloom has HDR values (> 1.0) to threshold against before tone mapping.
+const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+// The bloom chain works at a fraction of the window resolution: cheaper to
+// blur, and the downsample itself softens the glow.
+const BLOOM_DOWNSAMPLE: u32 = 2;
+
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct Vertex {
@@ -40,17 +68,27 @@ impl Vertex {
     }
 }
 
+// GPU-resident particle state. This doubles as both a storage buffer element
+// (read/written by the compute shader) and an instance vertex buffer element
+// (read by the vertex shader), so its layout must match `Particle` in
+// shader.wgsl exactly, including the unused-by-rendering `velocity` field.
+// `color` (a vec4) forces 16-byte struct alignment in WGSL's std430 layout,
+// so the 36 live bytes round up to a 48-byte stride; `_pad` makes that
+// explicit on the Rust side instead of leaving a silent 12-byte mismatch.
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-struct ParticleInstance {
+struct ParticleGpu {
     position: [f32; 2],
+    velocity: [f32; 2],
     color: [f32; 4],
+    size: f32,
+    _pad: [f32; 3],
 }
 
-impl ParticleInstance {
+impl ParticleGpu {
     fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<ParticleInstance>() as wgpu::BufferAddress,
+            array_stride: std::mem::size_of::<ParticleGpu>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Instance,
             attributes: &[
                 wgpu::VertexAttribute {
@@ -59,81 +97,449 @@ impl ParticleInstance {
                     format: wgpu::VertexFormat::Float32x2,
                 },
                 wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x4,
                 },
+                wgpu::VertexAttribute {
+                    offset: (std::mem::size_of::<[f32; 4]>() * 2) as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
             ],
         }
     }
 }
 
-struct Particle {
-    position: Vec2,
-    velocity: Vec2,
-    color: [f32; 4],
+// Mirrors `SimParams` in shader.wgsl field-for-field. `gravity` is the only
+// vec2 and it already lands on an 8-byte boundary, so no internal padding
+// is needed to satisfy std140 alignment. The struct's own size must still
+// round up to a 16-byte multiple to match WGSL's uniform address space
+// layout (19 scalars = 76 bytes, rounded to 80), hence the trailing `_pad`.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SimParams {
+    dt: f32,
+    bounce_damping: f32,
+    particle_size: f32,
+    mode: u32,
+    gravity: [f32; 2],
+    screen_width: f32,
+    screen_height: f32,
+    num_particles: u32,
+    max_speed: f32,
+    rule1_distance: f32,
+    rule2_distance: f32,
+    rule3_distance: f32,
+    rule1_scale: f32,
+    rule2_scale: f32,
+    rule3_scale: f32,
+    cell_size: f32,
+    grid_width: u32,
+    grid_height: u32,
+    _pad: f32,
 }
 
-impl Particle {
-    fn new(position: Vec2, velocity: Vec2, color: [f32; 4]) -> Self {
+// Mirrors `Uniforms` in shader.wgsl. Particle size now travels per-instance
+// (see `ParticleGpu::size`) rather than as a single uniform value, so this
+// is just the screen dimensions the vertex shader needs for its NDC math.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    screen_size: [f32; 2],
+}
+
+fn grid_dims(width: f32, height: f32) -> (u32, u32) {
+    (
+        ((width / GRID_CELL_SIZE).ceil() as u32).max(1),
+        ((height / GRID_CELL_SIZE).ceil() as u32).max(1),
+    )
+}
+
+// The per-cell grid buffers are sized from `grid_width`/`grid_height` (the
+// particle index buffer from `NUM_PARTICLES`); all four are recreated
+// together whenever the window (and therefore the grid) is resized.
+fn create_grid_buffers(
+    device: &wgpu::Device,
+    grid_width: u32,
+    grid_height: u32,
+) -> (wgpu::Buffer, wgpu::Buffer, wgpu::Buffer, wgpu::Buffer) {
+    let num_cells = (grid_width * grid_height) as u64;
+
+    let grid_counts_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Grid Counts Buffer"),
+        size: num_cells * std::mem::size_of::<u32>() as u64,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+    // One extra sentinel entry so `grid_cell_start[cell + 1]` is always
+    // valid, even for the last cell.
+    let grid_cell_start_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Grid Cell Start Buffer"),
+        size: (num_cells + 1) * std::mem::size_of::<u32>() as u64,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+    let grid_write_cursor_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Grid Write Cursor Buffer"),
+        size: num_cells * std::mem::size_of::<u32>() as u64,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+    let grid_particle_indices_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Grid Particle Indices Buffer"),
+        size: (NUM_PARTICLES * std::mem::size_of::<u32>()) as u64,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+
+    (
+        grid_counts_buffer,
+        grid_cell_start_buffer,
+        grid_write_cursor_buffer,
+        grid_particle_indices_buffer,
+    )
+}
+
+fn create_integrate_bind_groups(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    particle_buffers: &[wgpu::Buffer; 2],
+    sim_params_buffer: &wgpu::Buffer,
+    grid_cell_start_buffer: &wgpu::Buffer,
+    grid_particle_indices_buffer: &wgpu::Buffer,
+) -> [wgpu::BindGroup; 2] {
+    // bind group `i` reads from buffer `i` and writes to buffer `1 - i`,
+    // so swapping which bind group we dispatch each frame is the whole
+    // ping-pong: `pingpong ^= 1` after every dispatch.
+    [0, 1].map(|i| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Integrate Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: particle_buffers[i].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: particle_buffers[1 - i].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: sim_params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: grid_cell_start_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: grid_particle_indices_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_grid_bind_groups(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    particle_buffers: &[wgpu::Buffer; 2],
+    sim_params_buffer: &wgpu::Buffer,
+    grid_counts_buffer: &wgpu::Buffer,
+    grid_cell_start_buffer: &wgpu::Buffer,
+    grid_write_cursor_buffer: &wgpu::Buffer,
+    grid_particle_indices_buffer: &wgpu::Buffer,
+) -> [wgpu::BindGroup; 2] {
+    // Grid bind group `i` hashes the same buffer that integrate bind group
+    // `i` reads as its input, so the grid always reflects this frame's
+    // current (not-yet-integrated) positions.
+    [0, 1].map(|i| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Grid Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: particle_buffers[i].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: grid_counts_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: sim_params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: grid_cell_start_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: grid_write_cursor_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: grid_particle_indices_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    })
+}
+
+fn create_color_target_view(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    label: &str,
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: HDR_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+// Offscreen targets the particle pass and bloom chain render into. Grouped
+// together since every one of them depends on the window size and so gets
+// rebuilt as a unit whenever `resize` runs.
+struct OffscreenTargets {
+    hdr_view: wgpu::TextureView,
+    bright_view: wgpu::TextureView,
+    blur_h_view: wgpu::TextureView,
+    blur_v_view: wgpu::TextureView,
+}
+
+impl OffscreenTargets {
+    fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let half_width = width / BLOOM_DOWNSAMPLE;
+        let half_height = height / BLOOM_DOWNSAMPLE;
         Self {
-            position,
-            velocity,
-            color,
+            hdr_view: create_color_target_view(device, width, height, "HDR Target"),
+            bright_view: create_color_target_view(
+                device,
+                half_width,
+                half_height,
+                "Bloom Bright Target",
+            ),
+            blur_h_view: create_color_target_view(
+                device,
+                half_width,
+                half_height,
+                "Bloom Blur H Target",
+            ),
+            blur_v_view: create_color_target_view(
+                device,
+                half_width,
+                half_height,
+                "Bloom Blur V Target",
+            ),
         }
     }
+}
 
-    fn update(&mut self, dt: f32, width: f32, height: f32) {
-        // Apply gravity
-        self.velocity += GRAVITY * dt;
+// One entry in the chained post-process stage: a full-screen pass that
+// samples one input texture and writes a fixed intermediate target. The
+// final composite pass is not one of these entries: it has its own pipeline
+// and bind group and draws to the swapchain. Adding another filter to the bloom chain is
+// just another entry in the `Vec` built by `build_post_process_resources`.
+struct PostProcessPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    target: wgpu::TextureView,
+}
 
-        // Update position
-        self.position += self.velocity * dt;
+fn create_post_process_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    layout: &wgpu::PipelineLayout,
+    fragment_entry: &'static str,
+    target_format: wgpu::TextureFormat,
+    label: &str,
+) -> wgpu::RenderPipeline {
+    // Every post-process pass shares the same fullscreen-triangle vertex
+    // stage and differs only in which texture(s) it samples and writes.
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_fullscreen",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: fragment_entry,
+            targets: &[Some(wgpu::ColorTargetState {
+                format: target_format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
 
-        // Boundary collision detection and response
-        let radius = PARTICLE_SIZE / 2.0;
+fn create_post_single_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    source: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Post Process Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(source),
+            },
+        ],
+    })
+}
 
-        // Bottom boundary
-        if self.position.y + radius > height {
-            self.position.y = height - radius;
-            self.velocity.y = -self.velocity.y * BOUNCE_DAMPING;
-        }
+fn create_composite_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    base: &wgpu::TextureView,
+    bloom: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Composite Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(base),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(bloom),
+            },
+        ],
+    })
+}
 
-        // Top boundary
-        if self.position.y - radius < 0.0 {
-            self.position.y = radius;
-            self.velocity.y = -self.velocity.y * BOUNCE_DAMPING;
-        }
+// Bundles everything that depends on window size: the offscreen targets
+// themselves, the chained bright/blur passes that render into them, and the
+// composite bind group that reads the last of them back. Rebuilt wholesale
+// in both `State::new` and `State::resize`.
+struct PostProcessResources {
+    hdr_view: wgpu::TextureView,
+    passes: Vec<PostProcessPass>,
+    composite_bind_group: wgpu::BindGroup,
+}
 
-        // Right boundary
-        if self.position.x + radius > width {
-            self.position.x = width - radius;
-            self.velocity.x = -self.velocity.x * BOUNCE_DAMPING;
-        }
+#[allow(clippy::too_many_arguments)]
+fn build_post_process_resources(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    sampler: &wgpu::Sampler,
+    single_bind_group_layout: &wgpu::BindGroupLayout,
+    composite_bind_group_layout: &wgpu::BindGroupLayout,
+    bright_pipeline: &wgpu::RenderPipeline,
+    blur_h_pipeline: &wgpu::RenderPipeline,
+    blur_v_pipeline: &wgpu::RenderPipeline,
+) -> PostProcessResources {
+    let targets = OffscreenTargets::new(device, width, height);
 
-        // Left boundary
-        if self.position.x - radius < 0.0 {
-            self.position.x = radius;
-            self.velocity.x = -self.velocity.x * BOUNCE_DAMPING;
-        }
-    }
+    // Each bind group is created before the view it reads is moved into the
+    // pass that renders into it further down, so one pass's output can be
+    // wired as the next pass's input without the borrow checker caring
+    // about the order the fields end up stored in.
+    let bright_bind_group =
+        create_post_single_bind_group(device, single_bind_group_layout, sampler, &targets.hdr_view);
+    let blur_h_bind_group = create_post_single_bind_group(
+        device,
+        single_bind_group_layout,
+        sampler,
+        &targets.bright_view,
+    );
+    let blur_v_bind_group = create_post_single_bind_group(
+        device,
+        single_bind_group_layout,
+        sampler,
+        &targets.blur_h_view,
+    );
+    let composite_bind_group = create_composite_bind_group(
+        device,
+        composite_bind_group_layout,
+        sampler,
+        &targets.hdr_view,
+        &targets.blur_v_view,
+    );
 
-    fn to_instance(&self) -> ParticleInstance {
-        ParticleInstance {
-            position: [self.position.x, self.position.y],
-            color: self.color,
-        }
+    let passes = vec![
+        PostProcessPass {
+            pipeline: bright_pipeline.clone(),
+            bind_group: bright_bind_group,
+            target: targets.bright_view,
+        },
+        PostProcessPass {
+            pipeline: blur_h_pipeline.clone(),
+            bind_group: blur_h_bind_group,
+            target: targets.blur_h_view,
+        },
+        PostProcessPass {
+            pipeline: blur_v_pipeline.clone(),
+            bind_group: blur_v_bind_group,
+            target: targets.blur_v_view,
+        },
+    ];
+
+    PostProcessResources {
+        hdr_view: targets.hdr_view,
+        passes,
+        composite_bind_group,
     }
 }
 
 struct ParticleSimulation {
-    particles: Vec<Particle>,
+    initial_state: Vec<ParticleGpu>,
 }
 
 impl ParticleSimulation {
     fn new(width: f32, height: f32) -> Self {
         let mut rng = rand::thread_rng();
-        let mut particles = Vec::with_capacity(NUM_PARTICLES);
+        let mut initial_state = Vec::with_capacity(NUM_PARTICLES);
 
         for _ in 0..NUM_PARTICLES {
             let x = rng.gen_range(0.0..width);
@@ -146,24 +552,16 @@ impl ParticleSimulation {
             let g = rng.gen_range(0.3..1.0);
             let b = rng.gen_range(0.3..1.0);
 
-            particles.push(Particle::new(
-                Vec2::new(x, y),
-                Vec2::new(vx, vy),
-                [r, g, b, 1.0],
-            ));
-        }
-
-        Self { particles }
-    }
-
-    fn update(&mut self, dt: f32, width: f32, height: f32) {
-        for particle in &mut self.particles {
-            particle.update(dt, width, height);
+            initial_state.push(ParticleGpu {
+                position: [x, y],
+                velocity: [vx, vy],
+                color: [r, g, b, 1.0],
+                size: PARTICLE_SIZE,
+                _pad: [0.0; 3],
+            });
         }
-    }
 
-    fn get_instance_data(&self) -> Vec<ParticleInstance> {
-        self.particles.iter().map(|p| p.to_instance()).collect()
+        Self { initial_state }
     }
 }
 
@@ -175,8 +573,45 @@ struct State {
     size: winit::dpi::PhysicalSize<u32>,
     render_pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
-    simulation: ParticleSimulation,
-    instance_buffer: wgpu::Buffer,
+    uniforms: Uniforms,
+    uniforms_buffer: wgpu::Buffer,
+    render_bind_group: wgpu::BindGroup,
+    // Ping-pong particle storage buffers. Each frame's compute pass reads
+    // from one and writes to the other, then rendering draws from whichever
+    // buffer was just written.
+    particle_buffers: [wgpu::Buffer; 2],
+    integrate_pipeline: wgpu::ComputePipeline,
+    integrate_bind_group_layout: wgpu::BindGroupLayout,
+    integrate_bind_groups: [wgpu::BindGroup; 2],
+    pingpong: usize,
+    sim_params: SimParams,
+    sim_params_buffer: wgpu::Buffer,
+    // Uniform spatial grid, rebuilt from scratch every frame so the flocking
+    // kernel can scan nearby cells instead of every other particle.
+    grid_bind_group_layout: wgpu::BindGroupLayout,
+    grid_bind_groups: [wgpu::BindGroup; 2],
+    grid_clear_pipeline: wgpu::ComputePipeline,
+    grid_count_pipeline: wgpu::ComputePipeline,
+    grid_prefix_sum_pipeline: wgpu::ComputePipeline,
+    grid_scatter_pipeline: wgpu::ComputePipeline,
+    grid_counts_buffer: wgpu::Buffer,
+    grid_cell_start_buffer: wgpu::Buffer,
+    grid_write_cursor_buffer: wgpu::Buffer,
+    grid_particle_indices_buffer: wgpu::Buffer,
+    // Particles render into this offscreen HDR texture instead of the
+    // swapchain; the post-process chain below reads it back.
+    hdr_view: wgpu::TextureView,
+    post_sampler: wgpu::Sampler,
+    post_single_bind_group_layout: wgpu::BindGroupLayout,
+    post_composite_bind_group_layout: wgpu::BindGroupLayout,
+    bright_pipeline: wgpu::RenderPipeline,
+    blur_h_pipeline: wgpu::RenderPipeline,
+    blur_v_pipeline: wgpu::RenderPipeline,
+    // Bright-pass threshold -> horizontal blur -> vertical blur, in order.
+    // More filters can be appended here without touching `render`.
+    post_process_passes: Vec<PostProcessPass>,
+    composite_pipeline: wgpu::RenderPipeline,
+    composite_bind_group: wgpu::BindGroup,
 }
 
 impl State {
@@ -231,15 +666,203 @@ impl State {
 
         surface.configure(&device, &config);
 
-        // Initialize the particle simulation
+        // Initialize the particle simulation and upload its initial state into
+        // both ping-pong buffers. Only buffer 0 is read on the first frame,
+        // but buffer 1 needs a matching layout so it can be bound either way.
         let simulation = ParticleSimulation::new(size.width as f32, size.height as f32);
-        let instance_data = simulation.get_instance_data();
-        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Particle Instance Buffer"),
-            contents: bytemuck::cast_slice(&instance_data),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        let particle_buffers = [
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Particle Buffer A"),
+                contents: bytemuck::cast_slice(&simulation.initial_state),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            }),
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Particle Buffer B"),
+                contents: bytemuck::cast_slice(&simulation.initial_state),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            }),
+        ];
+
+        let (grid_width, grid_height) = grid_dims(size.width as f32, size.height as f32);
+
+        let sim_params = SimParams {
+            dt: 0.0,
+            bounce_damping: BOUNCE_DAMPING,
+            particle_size: PARTICLE_SIZE,
+            mode: SIM_MODE_GRAVITY,
+            gravity: [GRAVITY.x, GRAVITY.y],
+            screen_width: size.width as f32,
+            screen_height: size.height as f32,
+            num_particles: NUM_PARTICLES as u32,
+            max_speed: FLOCK_MAX_SPEED,
+            rule1_distance: FLOCK_RULE1_DISTANCE,
+            rule2_distance: FLOCK_RULE2_DISTANCE,
+            rule3_distance: FLOCK_RULE3_DISTANCE,
+            rule1_scale: FLOCK_RULE1_SCALE,
+            rule2_scale: FLOCK_RULE2_SCALE,
+            rule3_scale: FLOCK_RULE3_SCALE,
+            cell_size: GRID_CELL_SIZE,
+            grid_width,
+            grid_height,
+            _pad: 0.0,
+        };
+        let sim_params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sim Params Buffer"),
+            contents: bytemuck::cast_slice(&[sim_params]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
+        let (
+            grid_counts_buffer,
+            grid_cell_start_buffer,
+            grid_write_cursor_buffer,
+            grid_particle_indices_buffer,
+        ) = create_grid_buffers(&device, grid_width, grid_height);
+
+        let integrate_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Integrate Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let integrate_bind_groups = create_integrate_bind_groups(
+            &device,
+            &integrate_bind_group_layout,
+            &particle_buffers,
+            &sim_params_buffer,
+            &grid_cell_start_buffer,
+            &grid_particle_indices_buffer,
+        );
+
+        let grid_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Grid Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let grid_bind_groups = create_grid_bind_groups(
+            &device,
+            &grid_bind_group_layout,
+            &particle_buffers,
+            &sim_params_buffer,
+            &grid_counts_buffer,
+            &grid_cell_start_buffer,
+            &grid_write_cursor_buffer,
+            &grid_particle_indices_buffer,
+        );
+
         // Define the vertices of a square to be instanced for each particle
         let vertex_data = [
             Vertex { position: [-0.5, -0.5] },
@@ -256,15 +879,97 @@ impl State {
             usage: wgpu::BufferUsages::VERTEX,
         });
 
-        // Create the shader module
+        // Create the shader module. It holds every entry point: the grid-build
+        // and integration compute kernels, the particle vertex/fragment
+        // stages, and the post-process passes.
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
             source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
         });
 
+        let integrate_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Integrate Pipeline Layout"),
+                bind_group_layouts: &[&integrate_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let integrate_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Particle Integration Pipeline"),
+            layout: Some(&integrate_pipeline_layout),
+            module: &shader,
+            entry_point: "cs_main",
+        });
+
+        let grid_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Grid Pipeline Layout"),
+            bind_group_layouts: &[&grid_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let grid_clear_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Grid Clear Pipeline"),
+            layout: Some(&grid_pipeline_layout),
+            module: &shader,
+            entry_point: "cs_grid_clear",
+        });
+        let grid_count_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Grid Count Pipeline"),
+            layout: Some(&grid_pipeline_layout),
+            module: &shader,
+            entry_point: "cs_grid_count",
+        });
+        let grid_prefix_sum_pipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Grid Prefix Sum Pipeline"),
+                layout: Some(&grid_pipeline_layout),
+                module: &shader,
+                entry_point: "cs_grid_prefix_sum",
+            });
+        let grid_scatter_pipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Grid Scatter Pipeline"),
+                layout: Some(&grid_pipeline_layout),
+                module: &shader,
+                entry_point: "cs_grid_scatter",
+            });
+
+        let uniforms = Uniforms {
+            screen_size: [size.width as f32, size.height as f32],
+        };
+        let uniforms_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms Buffer"),
+            contents: bytemuck::cast_slice(&[uniforms]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let render_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Render Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Render Bind Group"),
+            layout: &render_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms_buffer.as_entire_binding(),
+            }],
+        });
+
         let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[],
+            bind_group_layouts: &[&render_bind_group_layout],
             push_constant_ranges: &[],
         });
 
@@ -274,16 +979,29 @@ impl State {
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
-                buffers: &[Vertex::desc(), ParticleInstance::desc()],
+                buffers: &[Vertex::desc(), ParticleGpu::desc()],
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: "fs_main",
+                // Renders into the offscreen HDR target, not the swapchain;
+                // the post-process chain composites the result afterward.
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
+                    format: HDR_FORMAT,
+                    // Additive, alpha-weighted: overlapping soft sprites
+                    // accumulate brightness instead of the last one drawn
+                    // simply replacing the pixel underneath it.
                     blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent::REPLACE,
-                        alpha: wgpu::BlendComponent::REPLACE,
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
                     }),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -306,6 +1024,131 @@ impl State {
             multiview: None,
         });
 
+        let post_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Process Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let post_single_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Post Process Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let post_composite_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Composite Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let post_single_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Post Process Pipeline Layout"),
+                bind_group_layouts: &[&post_single_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let composite_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Composite Pipeline Layout"),
+                bind_group_layouts: &[&post_composite_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let bright_pipeline = create_post_process_pipeline(
+            &device,
+            &shader,
+            &post_single_pipeline_layout,
+            "fs_bright",
+            HDR_FORMAT,
+            "Bright Pass Pipeline",
+        );
+        let blur_h_pipeline = create_post_process_pipeline(
+            &device,
+            &shader,
+            &post_single_pipeline_layout,
+            "fs_blur_h",
+            HDR_FORMAT,
+            "Blur Horizontal Pipeline",
+        );
+        let blur_v_pipeline = create_post_process_pipeline(
+            &device,
+            &shader,
+            &post_single_pipeline_layout,
+            "fs_blur_v",
+            HDR_FORMAT,
+            "Blur Vertical Pipeline",
+        );
+        let composite_pipeline = create_post_process_pipeline(
+            &device,
+            &shader,
+            &composite_pipeline_layout,
+            "fs_composite",
+            config.format,
+            "Composite Pipeline",
+        );
+
+        let post_process = build_post_process_resources(
+            &device,
+            size.width,
+            size.height,
+            &post_sampler,
+            &post_single_bind_group_layout,
+            &post_composite_bind_group_layout,
+            &bright_pipeline,
+            &blur_h_pipeline,
+            &blur_v_pipeline,
+        );
+
         Self {
             surface,
             device,
@@ -314,8 +1157,36 @@ impl State {
             size,
             render_pipeline,
             vertex_buffer,
-            simulation,
-            instance_buffer,
+            uniforms,
+            uniforms_buffer,
+            render_bind_group,
+            particle_buffers,
+            integrate_pipeline,
+            integrate_bind_group_layout,
+            integrate_bind_groups,
+            pingpong: 0,
+            sim_params,
+            sim_params_buffer,
+            grid_bind_group_layout,
+            grid_bind_groups,
+            grid_clear_pipeline,
+            grid_count_pipeline,
+            grid_prefix_sum_pipeline,
+            grid_scatter_pipeline,
+            grid_counts_buffer,
+            grid_cell_start_buffer,
+            grid_write_cursor_buffer,
+            grid_particle_indices_buffer,
+            hdr_view: post_process.hdr_view,
+            post_sampler,
+            post_single_bind_group_layout,
+            post_composite_bind_group_layout,
+            bright_pipeline,
+            blur_h_pipeline,
+            blur_v_pipeline,
+            post_process_passes: post_process.passes,
+            composite_pipeline,
+            composite_bind_group: post_process.composite_bind_group,
         }
     }
 
@@ -325,75 +1196,141 @@ impl State {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
-            
-            // Recreate the shader with new dimensions
-            let shader_code = include_str!("shader_code.wgsl")
-                .replace("PARTICLE_SIZE: f32 = 3.0", &format!("PARTICLE_SIZE: f32 = {}", PARTICLE_SIZE))
-                .replace("SCREEN_WIDTH: f32 = 800.0", &format!("SCREEN_WIDTH: f32 = {}", new_size.width as f32))
-                .replace("SCREEN_HEIGHT: f32 = 600.0", &format!("SCREEN_HEIGHT: f32 = {}", new_size.height as f32));
-                
-            let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Some("Shader"),
-                source: wgpu::ShaderSource::Wgsl(shader_code.into()),
-            });
-            
-            // Recreate the render pipeline with the new shader
-            let render_pipeline_layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[],
-                push_constant_ranges: &[],
-            });
-            
-            self.render_pipeline = self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
-                layout: Some(&render_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: "vs_main",
-                    buffers: &[Vertex::desc(), ParticleInstance::desc()],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: "fs_main",
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: self.config.format,
-                        blend: Some(wgpu::BlendState {
-                            color: wgpu::BlendComponent::REPLACE,
-                            alpha: wgpu::BlendComponent::REPLACE,
-                        }),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: 1,
-                    mask: !0,
-                    alpha_to_coverage_enabled: false,
-                },
-                multiview: None,
-            });
+
+            self.sim_params.screen_width = new_size.width as f32;
+            self.sim_params.screen_height = new_size.height as f32;
+            let (grid_width, grid_height) =
+                grid_dims(new_size.width as f32, new_size.height as f32);
+            self.sim_params.grid_width = grid_width;
+            self.sim_params.grid_height = grid_height;
+            self.queue.write_buffer(
+                &self.sim_params_buffer,
+                0,
+                bytemuck::cast_slice(&[self.sim_params]),
+            );
+
+            // The grid buffers are sized from grid_width/grid_height, so they
+            // (and the bind groups pointing at them) need to be rebuilt.
+            let (
+                grid_counts_buffer,
+                grid_cell_start_buffer,
+                grid_write_cursor_buffer,
+                grid_particle_indices_buffer,
+            ) = create_grid_buffers(&self.device, grid_width, grid_height);
+            self.integrate_bind_groups = create_integrate_bind_groups(
+                &self.device,
+                &self.integrate_bind_group_layout,
+                &self.particle_buffers,
+                &self.sim_params_buffer,
+                &grid_cell_start_buffer,
+                &grid_particle_indices_buffer,
+            );
+            self.grid_bind_groups = create_grid_bind_groups(
+                &self.device,
+                &self.grid_bind_group_layout,
+                &self.particle_buffers,
+                &self.sim_params_buffer,
+                &grid_counts_buffer,
+                &grid_cell_start_buffer,
+                &grid_write_cursor_buffer,
+                &grid_particle_indices_buffer,
+            );
+            self.grid_counts_buffer = grid_counts_buffer;
+            self.grid_cell_start_buffer = grid_cell_start_buffer;
+            self.grid_write_cursor_buffer = grid_write_cursor_buffer;
+            self.grid_particle_indices_buffer = grid_particle_indices_buffer;
+
+            // No shader recompilation or pipeline recreation needed: the
+            // render pipeline reads screen size from `uniforms`, so resizing
+            // is just a buffer write.
+            self.uniforms.screen_size = [new_size.width as f32, new_size.height as f32];
+            self.queue.write_buffer(
+                &self.uniforms_buffer,
+                0,
+                bytemuck::cast_slice(&[self.uniforms]),
+            );
+
+            // The offscreen HDR target and bloom chain's textures are all
+            // sized from the window, so they (and the bind groups pointing
+            // at them) need rebuilding too. The post-process pipelines
+            // themselves don't depend on window size, so they're untouched.
+            let post_process = build_post_process_resources(
+                &self.device,
+                new_size.width,
+                new_size.height,
+                &self.post_sampler,
+                &self.post_single_bind_group_layout,
+                &self.post_composite_bind_group_layout,
+                &self.bright_pipeline,
+                &self.blur_h_pipeline,
+                &self.blur_v_pipeline,
+            );
+            self.hdr_view = post_process.hdr_view;
+            self.post_process_passes = post_process.passes;
+            self.composite_bind_group = post_process.composite_bind_group;
         }
     }
 
-    fn update(&mut self, dt: f32) {
-        self.simulation.update(dt, self.size.width as f32, self.size.height as f32);
+    fn toggle_sim_mode(&mut self) {
+        self.sim_params.mode = if self.sim_params.mode == SIM_MODE_GRAVITY {
+            SIM_MODE_FLOCKING
+        } else {
+            SIM_MODE_GRAVITY
+        };
+    }
 
-        // Update instance buffer with new particle positions
-        let instance_data = self.simulation.get_instance_data();
+    fn update(&mut self, dt: f32) {
+        self.sim_params.dt = dt;
         self.queue.write_buffer(
-            &self.instance_buffer,
+            &self.sim_params_buffer,
             0,
-            bytemuck::cast_slice(&instance_data),
+            bytemuck::cast_slice(&[self.sim_params]),
         );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Compute Encoder"),
+            });
+
+        let num_cells = self.sim_params.grid_width * self.sim_params.grid_height;
+        let particle_workgroups = (NUM_PARTICLES as u32).div_ceil(WORKGROUP_SIZE);
+        let cell_workgroups = num_cells.div_ceil(WORKGROUP_SIZE);
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Particle Update Pass"),
+            });
+
+            // Rebuild the spatial grid from this frame's current positions
+            // before the integration dispatch reads it. wgpu inserts the
+            // barriers needed for each dispatch to see the previous one's
+            // writes, so these can all share one compute pass. Only the
+            // flocking kernel reads the grid, so skip rebuilding it in
+            // gravity mode rather than pay the counting-sort cost for
+            // nothing.
+            if self.sim_params.mode == SIM_MODE_FLOCKING {
+                compute_pass.set_bind_group(0, &self.grid_bind_groups[self.pingpong], &[]);
+                compute_pass.set_pipeline(&self.grid_clear_pipeline);
+                compute_pass.dispatch_workgroups(cell_workgroups, 1, 1);
+                compute_pass.set_pipeline(&self.grid_count_pipeline);
+                compute_pass.dispatch_workgroups(particle_workgroups, 1, 1);
+                compute_pass.set_pipeline(&self.grid_prefix_sum_pipeline);
+                compute_pass.dispatch_workgroups(1, 1, 1);
+                compute_pass.set_pipeline(&self.grid_scatter_pipeline);
+                compute_pass.dispatch_workgroups(particle_workgroups, 1, 1);
+            }
+
+            compute_pass.set_pipeline(&self.integrate_pipeline);
+            compute_pass.set_bind_group(0, &self.integrate_bind_groups[self.pingpong], &[]);
+            compute_pass.dispatch_workgroups(particle_workgroups, 1, 1);
+        }
+
+        self.queue.submit(iter::once(encoder.finish()));
+
+        // The buffer at `1 - pingpong` now holds this frame's integrated
+        // state; flip so rendering (and the next dispatch's input) reads it.
+        self.pingpong = 1 - self.pingpong;
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
@@ -409,10 +1346,12 @@ impl State {
             });
 
         {
+            // Particles render into the offscreen HDR target instead of the
+            // swapchain; the passes below composite that into `view`.
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
+                label: Some("Particle Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: &self.hdr_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
@@ -428,11 +1367,55 @@ impl State {
             });
 
             render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.render_bind_group, &[]);
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.particle_buffers[self.pingpong].slice(..));
             render_pass.draw(0..6, 0..NUM_PARTICLES as u32);
         }
 
+        // Bright-pass threshold -> horizontal blur -> vertical blur. Each
+        // pass is a full-screen triangle with no vertex buffer, drawn into
+        // its own fixed intermediate texture.
+        for pass in &self.post_process_passes {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post Process Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &pass.target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&pass.pipeline);
+            render_pass.set_bind_group(0, &pass.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        {
+            // Add the blurred bloom back over the original HDR image and
+            // tone-map to the swapchain's LDR range.
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Composite Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&self.composite_pipeline);
+            render_pass.set_bind_group(0, &self.composite_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
         self.queue.submit(iter::once(encoder.finish()));
         output.present();
 
@@ -467,6 +1450,17 @@ fn main() {
                         },
                     ..
                 } => *control_flow = ControlFlow::Exit,
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(VirtualKeyCode::Space),
+                            ..
+                        },
+                    ..
+                } => {
+                    state.toggle_sim_mode();
+                }
                 WindowEvent::Resized(physical_size) => {
                     state.resize(*physical_size);
                 }